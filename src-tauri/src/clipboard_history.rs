@@ -1,165 +1,492 @@
-use rusqlite::Connection;
+use arboard::Clipboard;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use sqlx::sqlite::SqlitePool;
+use sqlx::Row;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 use tauri::async_runtime;
 
+/// Longest edge, in pixels, of the thumbnail stored alongside a full-size clipboard image
+const THUMBNAIL_MAX_DIM: u32 = 200;
+
+/// Default window in which a repeated hash is treated as the same copy and bumps the
+/// existing row's `created_at` instead of inserting a new one
+pub const DEFAULT_DEDUP_WINDOW_SECS: i64 = 60;
+
+/// Hash arbitrary content bytes for dedup. Two entries with the same hash and content
+/// type within the dedup window are considered the same copy.
+pub(crate) fn content_hash(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The clipboard payload itself, tagged so the frontend can tell text and image
+/// entries apart without inspecting raw bytes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "content_type", rename_all = "lowercase")]
+pub enum ClipboardContent {
+    Text {
+        content: String,
+    },
+    Image {
+        /// Base64-encoded full-resolution PNG
+        data: String,
+        /// Base64-encoded thumbnail PNG, sized for list rendering
+        preview_data: String,
+        width: u32,
+        height: u32,
+    },
+    Files {
+        paths: Vec<String>,
+    },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClipboardEntry {
     pub id: i64,
-    pub content: String,
-    pub content_type: String, // "text", "image", etc.
+    /// Stable identity for this entry, independent of the local autoincrement `id`,
+    /// so synced copies of the same entry on different devices can be reconciled
+    pub uuid: String,
+    #[serde(flatten)]
+    pub content: ClipboardContent,
     pub created_at: String,
-    pub preview: String, // First 100 chars for display
-    pub char_count: i32,
+    pub preview: String, // First 100 chars for text, or a "WxH" caption for images
+    pub char_count: Option<i32>, // NULL for images
 }
 
-/// Initialize the clipboard_history table in the database
-pub fn init_clipboard_table(conn: &Connection) -> Result<(), rusqlite::Error> {
-    conn.execute(
+/// Add `column` to `clipboard_history` if an existing install doesn't already have
+/// it. `CREATE TABLE IF NOT EXISTS` alone is a no-op against a table that already
+/// exists, so every column added after the original baseline schema has to be
+/// migrated in explicitly rather than just appended to the `CREATE TABLE` literal.
+async fn ensure_column(pool: &SqlitePool, column: &str, decl: &str) -> Result<(), sqlx::Error> {
+    let exists = sqlx::query("SELECT 1 FROM pragma_table_info('clipboard_history') WHERE name = ?")
+        .bind(column)
+        .fetch_optional(pool)
+        .await?
+        .is_some();
+
+    if !exists {
+        sqlx::query(&format!("ALTER TABLE clipboard_history ADD COLUMN {} {}", column, decl))
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Assign a stable uuid to any row left over from before the `uuid` column existed.
+/// Every read of the column is a non-`Option<_>` `try_get`, which errors on a SQL
+/// `NULL` rather than decoding it as empty, so a legacy row has to be backfilled here
+/// rather than left `NULL`.
+async fn backfill_missing_uuids(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let ids: Vec<i64> = sqlx::query_scalar("SELECT id FROM clipboard_history WHERE uuid IS NULL")
+        .fetch_all(pool)
+        .await?;
+
+    for id in ids {
+        sqlx::query("UPDATE clipboard_history SET uuid = ? WHERE id = ?")
+            .bind(uuid::Uuid::new_v4().to_string())
+            .bind(id)
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Initialize the clipboard_history table, its indexes, and the FTS5 index used by
+/// `search_clipboard_entries`
+pub async fn init_clipboard_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // Original baseline schema. Everything added since is migrated in below via
+    // `ensure_column`, so an existing install picks up new columns instead of the
+    // inserts that reference them failing with "no such column".
+    sqlx::query(
         "CREATE TABLE IF NOT EXISTS clipboard_history (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
-            content TEXT NOT NULL,
-            content_type TEXT DEFAULT 'text',
+            content TEXT,
             created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
             char_count INTEGER
         )",
-        [],
-    )?;
-
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_clipboard_created_at ON clipboard_history(created_at DESC)",
-        [],
-    )?;
+    )
+    .execute(pool)
+    .await?;
+
+    ensure_column(pool, "uuid", "TEXT").await?;
+    backfill_missing_uuids(pool).await?;
+    ensure_column(pool, "content_type", "TEXT DEFAULT 'text'").await?;
+    ensure_column(pool, "content_blob", "BLOB").await?;
+    ensure_column(pool, "content_hash", "INTEGER").await?;
+    ensure_column(pool, "preview_blob", "BLOB").await?;
+    ensure_column(pool, "preview", "TEXT").await?;
+    ensure_column(pool, "width", "INTEGER").await?;
+    ensure_column(pool, "height", "INTEGER").await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_clipboard_created_at ON clipboard_history(created_at DESC)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_clipboard_content_hash ON clipboard_history(content_hash)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_clipboard_uuid ON clipboard_history(uuid)")
+        .execute(pool)
+        .await?;
+
+    // External-content FTS5 index over `content`, kept in sync via triggers so search
+    // is index-backed instead of a `LIKE '%...%'` table scan.
+    sqlx::query(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS clipboard_fts USING fts5(
+            content,
+            content='clipboard_history',
+            content_rowid='id'
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TRIGGER IF NOT EXISTS clipboard_fts_ai AFTER INSERT ON clipboard_history BEGIN
+            INSERT INTO clipboard_fts(rowid, content) VALUES (new.id, new.content);
+        END",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TRIGGER IF NOT EXISTS clipboard_fts_ad AFTER DELETE ON clipboard_history BEGIN
+            INSERT INTO clipboard_fts(clipboard_fts, rowid, content) VALUES('delete', old.id, old.content);
+        END",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TRIGGER IF NOT EXISTS clipboard_fts_au AFTER UPDATE ON clipboard_history BEGIN
+            INSERT INTO clipboard_fts(clipboard_fts, rowid, content) VALUES('delete', old.id, old.content);
+            INSERT INTO clipboard_fts(rowid, content) VALUES (new.id, new.content);
+        END",
+    )
+    .execute(pool)
+    .await?;
 
     Ok(())
 }
 
-/// Add a new clipboard entry, avoiding duplicates of the most recent entry
-pub fn add_clipboard_entry(
-    conn: &Connection,
+/// Find the most recent row of the given content type whose hash matches, within the
+/// dedup window, so a repeated copy can bump it instead of inserting a duplicate row
+async fn find_recent_duplicate(
+    pool: &SqlitePool,
+    content_type: &str,
+    hash: i64,
+    dedup_window_secs: i64,
+) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT id FROM clipboard_history
+         WHERE content_type = ? AND content_hash = ?
+           AND created_at >= datetime('now', ?)
+         ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(content_type)
+    .bind(hash)
+    .bind(format!("-{} seconds", dedup_window_secs))
+    .fetch_optional(pool)
+    .await
+}
+
+/// Find an existing row of the given content type with the same hash, irrespective of
+/// when it was created. Used by sync to collapse a copy pulled from another device
+/// into the row already present locally instead of inserting a duplicate.
+pub(crate) async fn find_duplicate_by_hash(
+    pool: &SqlitePool,
+    content_type: &str,
+    hash: i64,
+) -> Result<Option<(i64, String)>, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT id, created_at FROM clipboard_history
+         WHERE content_type = ? AND content_hash = ?
+         ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(content_type)
+    .bind(hash)
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(|row| Ok((row.try_get("id")?, row.try_get("created_at")?))).transpose()
+}
+
+/// Add a new clipboard entry. Dedup looks up the indexed `content_hash` instead of
+/// loading and comparing full content: a row with the same `content_hash` within
+/// `dedup_window_secs` just has its `created_at` bumped instead of a new row being
+/// inserted.
+pub async fn add_clipboard_entry(
+    pool: &SqlitePool,
     content: &str,
     content_type: &str,
-) -> Result<Option<i64>, rusqlite::Error> {
+    dedup_window_secs: i64,
+) -> Result<Option<i64>, sqlx::Error> {
     // Skip empty content
     if content.trim().is_empty() {
         return Ok(None);
     }
 
-    // Check if the most recent entry has the same content (avoid duplicates)
-    let mut stmt = conn.prepare(
-        "SELECT content FROM clipboard_history ORDER BY created_at DESC LIMIT 1",
-    )?;
+    let hash = content_hash(content.as_bytes()) as i64;
 
-    let last_content: Option<String> = stmt
-        .query_row([], |row| row.get(0))
-        .ok();
-
-    if let Some(last) = last_content {
-        if last == content {
-            return Ok(None); // Skip duplicate
-        }
+    if let Some(existing_id) = find_recent_duplicate(pool, content_type, hash, dedup_window_secs).await? {
+        sqlx::query("UPDATE clipboard_history SET created_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(existing_id)
+            .execute(pool)
+            .await?;
+        return Ok(None); // Bumped the existing row instead of inserting a duplicate
     }
 
     let char_count = content.chars().count() as i32;
+    let uuid = uuid::Uuid::new_v4().to_string();
+
+    let result = sqlx::query(
+        "INSERT INTO clipboard_history (uuid, content, content_type, content_hash, char_count)
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(uuid)
+    .bind(content)
+    .bind(content_type)
+    .bind(hash)
+    .bind(char_count)
+    .execute(pool)
+    .await?;
+
+    Ok(Some(result.last_insert_rowid()))
+}
+
+/// Add a new image clipboard entry. See [`add_clipboard_entry`] for the hash-based
+/// dedup strategy; here the hash is computed over the raw RGBA pixel buffer.
+pub async fn add_clipboard_image_entry(
+    pool: &SqlitePool,
+    width: u32,
+    height: u32,
+    rgba: &[u8],
+    dedup_window_secs: i64,
+) -> Result<Option<i64>, sqlx::Error> {
+    let hash = content_hash(rgba) as i64;
+
+    if let Some(existing_id) = find_recent_duplicate(pool, "image", hash, dedup_window_secs).await? {
+        sqlx::query("UPDATE clipboard_history SET created_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(existing_id)
+            .execute(pool)
+            .await?;
+        return Ok(None); // Bumped the existing row instead of inserting a duplicate
+    }
+
+    let png = encode_png(width, height, rgba)?;
+    let thumbnail = encode_thumbnail(width, height, rgba)?;
+    let preview = format!("Image ({}\u{00d7}{})", width, height);
+    let uuid = uuid::Uuid::new_v4().to_string();
+
+    let result = sqlx::query(
+        "INSERT INTO clipboard_history
+            (uuid, content_type, content_blob, content_hash, preview_blob, preview, width, height)
+         VALUES (?, 'image', ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(uuid)
+    .bind(png)
+    .bind(hash)
+    .bind(thumbnail)
+    .bind(preview)
+    .bind(width as i64)
+    .bind(height as i64)
+    .execute(pool)
+    .await?;
+
+    Ok(Some(result.last_insert_rowid()))
+}
 
-    conn.execute(
-        "INSERT INTO clipboard_history (content, content_type, char_count)
-         VALUES (?1, ?2, ?3)",
-        rusqlite::params![content, content_type, char_count],
-    )?;
+/// Encode raw RGBA clipboard pixels to PNG bytes
+fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Result<Vec<u8>, sqlx::Error> {
+    let image = image::RgbaImage::from_raw(width, height, rgba.to_vec()).ok_or_else(|| {
+        sqlx::Error::Protocol("clipboard image dimensions did not match the pixel buffer".into())
+    })?;
 
-    Ok(Some(conn.last_insert_rowid()))
+    let mut bytes = Vec::new();
+    image::DynamicImage::ImageRgba8(image)
+        .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+
+    Ok(bytes)
+}
+
+/// Encode a thumbnail-sized PNG preview from raw RGBA clipboard pixels
+fn encode_thumbnail(width: u32, height: u32, rgba: &[u8]) -> Result<Vec<u8>, sqlx::Error> {
+    let image = image::RgbaImage::from_raw(width, height, rgba.to_vec()).ok_or_else(|| {
+        sqlx::Error::Protocol("clipboard image dimensions did not match the pixel buffer".into())
+    })?;
+    // `DynamicImage::thumbnail` keeps the aspect ratio; `imageops::thumbnail` would
+    // stretch to exactly THUMBNAIL_MAX_DIM x THUMBNAIL_MAX_DIM
+    let thumbnail = image::DynamicImage::ImageRgba8(image).thumbnail(THUMBNAIL_MAX_DIM, THUMBNAIL_MAX_DIM);
+
+    let mut bytes = Vec::new();
+    thumbnail
+        .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+
+    Ok(bytes)
+}
+
+/// Build a `ClipboardEntry` from a `clipboard_history` row, decoding the tagged
+/// content based on `content_type`
+fn row_to_clipboard_entry(row: &sqlx::sqlite::SqliteRow) -> Result<ClipboardEntry, sqlx::Error> {
+    let id: i64 = row.try_get("id")?;
+    let uuid: String = row.try_get("uuid")?;
+    let content_type: String = row.try_get("content_type")?;
+    let created_at: String = row.try_get("created_at")?;
+    let char_count: Option<i32> = row.try_get("char_count")?;
+
+    let (content, preview) = if content_type == "image" {
+        let data: Vec<u8> = row.try_get("content_blob")?;
+        let preview_data: Vec<u8> = row.try_get("preview_blob")?;
+        let preview: String = row.try_get("preview")?;
+        let width: i64 = row.try_get("width")?;
+        let height: i64 = row.try_get("height")?;
+        (
+            ClipboardContent::Image {
+                data: BASE64.encode(data),
+                preview_data: BASE64.encode(preview_data),
+                width: width as u32,
+                height: height as u32,
+            },
+            preview,
+        )
+    } else if content_type == "files" {
+        let content: String = row.try_get("content")?;
+        let paths: Vec<String> = content.lines().map(str::to_string).collect();
+        let preview = files_preview(&paths);
+        (ClipboardContent::Files { paths }, preview)
+    } else {
+        let content: String = row.try_get("content")?;
+        let preview = create_preview(&content, 100);
+        (ClipboardContent::Text { content }, preview)
+    };
+
+    Ok(ClipboardEntry {
+        id,
+        uuid,
+        content,
+        created_at,
+        preview,
+        char_count,
+    })
 }
 
 /// Get clipboard history entries
-pub fn get_clipboard_entries(
-    conn: &Connection,
+pub async fn get_clipboard_entries(
+    pool: &SqlitePool,
     limit: u32,
-) -> Result<Vec<ClipboardEntry>, rusqlite::Error> {
-    let mut stmt = conn.prepare(
-        "SELECT id, content, content_type, created_at, char_count
+) -> Result<Vec<ClipboardEntry>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, uuid, content, content_type, content_blob, preview_blob, width, height, created_at, char_count, preview
          FROM clipboard_history
          ORDER BY created_at DESC
-         LIMIT ?1",
-    )?;
-
-    let entries = stmt
-        .query_map(rusqlite::params![limit], |row| {
-            let content: String = row.get(1)?;
-            let preview = create_preview(&content, 100);
-            Ok(ClipboardEntry {
-                id: row.get(0)?,
-                content,
-                content_type: row.get(2)?,
-                created_at: row.get(3)?,
-                preview,
-                char_count: row.get(4)?,
-            })
-        })?
-        .collect::<Result<Vec<_>, _>>()?;
-
-    Ok(entries)
-}
-
-/// Search clipboard history
-pub fn search_clipboard_entries(
-    conn: &Connection,
+         LIMIT ?",
+    )
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(row_to_clipboard_entry).collect()
+}
+
+/// Search clipboard history via the `clipboard_fts` index. Each whitespace-separated
+/// term is matched as a prefix, so e.g. "há" matches "hash_map" in the same way atuin's
+/// history search matches partial commands.
+pub async fn search_clipboard_entries(
+    pool: &SqlitePool,
     query: &str,
     limit: u32,
-) -> Result<Vec<ClipboardEntry>, rusqlite::Error> {
-    let search_pattern = format!("%{}%", query);
-    let mut stmt = conn.prepare(
-        "SELECT id, content, content_type, created_at, char_count
-         FROM clipboard_history
-         WHERE content LIKE ?1
-         ORDER BY created_at DESC
-         LIMIT ?2",
-    )?;
-
-    let entries = stmt
-        .query_map(rusqlite::params![search_pattern, limit], |row| {
-            let content: String = row.get(1)?;
-            let preview = create_preview(&content, 100);
-            Ok(ClipboardEntry {
-                id: row.get(0)?,
-                content,
-                content_type: row.get(2)?,
-                created_at: row.get(3)?,
-                preview,
-                char_count: row.get(4)?,
-            })
-        })?
-        .collect::<Result<Vec<_>, _>>()?;
-
-    Ok(entries)
+) -> Result<Vec<ClipboardEntry>, sqlx::Error> {
+    let fts_query = build_fts_query(query);
+    if fts_query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rows = sqlx::query(
+        "SELECT ch.id, ch.uuid, ch.content, ch.content_type, ch.content_blob, ch.preview_blob,
+                ch.width, ch.height, ch.created_at, ch.char_count, ch.preview
+         FROM clipboard_fts
+         JOIN clipboard_history ch ON ch.id = clipboard_fts.rowid
+         WHERE clipboard_fts MATCH ?
+         ORDER BY ch.created_at DESC
+         LIMIT ?",
+    )
+    .bind(fts_query)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(row_to_clipboard_entry).collect()
+}
+
+/// Turn a free-text search query into an FTS5 MATCH expression: each term becomes a
+/// prefix match, so partial words still hit
+fn build_fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 /// Delete a clipboard entry
-pub fn delete_clipboard_entry(conn: &Connection, id: i64) -> Result<(), rusqlite::Error> {
-    conn.execute(
-        "DELETE FROM clipboard_history WHERE id = ?1",
-        rusqlite::params![id],
-    )?;
+pub async fn delete_clipboard_entry(pool: &SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM clipboard_history WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
     Ok(())
 }
 
 /// Clear all clipboard history
-pub fn clear_clipboard_history(conn: &Connection) -> Result<(), rusqlite::Error> {
-    conn.execute("DELETE FROM clipboard_history", [])?;
+pub async fn clear_clipboard_history(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM clipboard_history").execute(pool).await?;
     Ok(())
 }
 
 /// Cleanup old entries, keeping only the most recent N entries
-pub fn cleanup_clipboard_history(conn: &Connection, max_entries: u32) -> Result<u32, rusqlite::Error> {
-    let result = conn.execute(
+pub async fn cleanup_clipboard_history(pool: &SqlitePool, max_entries: u32) -> Result<u32, sqlx::Error> {
+    let result = sqlx::query(
         "DELETE FROM clipboard_history WHERE id NOT IN (
-            SELECT id FROM clipboard_history ORDER BY created_at DESC LIMIT ?1
+            SELECT id FROM clipboard_history ORDER BY created_at DESC LIMIT ?
         )",
-        rusqlite::params![max_entries],
-    )?;
-    Ok(result as u32)
+    )
+    .bind(max_entries as i64)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() as u32)
+}
+
+/// Build a preview for a "files" entry: the count plus the first three basenames,
+/// e.g. "4 files: a.txt, b.png, c.pdf, ..."
+fn files_preview(paths: &[String]) -> String {
+    let basenames: Vec<&str> = paths
+        .iter()
+        .map(|p| p.rsplit(['/', '\\']).next().unwrap_or(p.as_str()))
+        .collect();
+
+    let shown = basenames.iter().take(3).copied().collect::<Vec<_>>().join(", ");
+    let ellipsis = if basenames.len() > 3 { ", ..." } else { "" };
+
+    format!(
+        "{} file{}: {}{}",
+        basenames.len(),
+        if basenames.len() == 1 { "" } else { "s" },
+        shown,
+        ellipsis
+    )
 }
 
 /// Create a preview string from content
@@ -178,24 +505,109 @@ fn create_preview(content: &str, max_len: usize) -> String {
     }
 }
 
+/// Why a system clipboard read failed
+#[derive(Debug, Clone)]
+pub enum ClipboardBackend {
+    /// No clipboard backend is reachable on this platform/session (e.g. no X11/Wayland
+    /// display, or the OS API rejected the request)
+    Unavailable(String),
+}
+
+impl std::fmt::Display for ClipboardBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClipboardBackend::Unavailable(reason) => {
+                write!(f, "clipboard backend unavailable: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ClipboardBackend {}
+
+/// A single clipboard read, classified by what the monitor found
+pub enum ContentKind {
+    Text(String),
+    Image { width: u32, height: u32, rgba: Vec<u8> },
+    Files(Vec<String>),
+}
+
+/// Classify a text clipboard read as plain text or a file list. Text/X11/Wayland
+/// clipboards commonly represent a file-manager copy as a `text/uri-list` of
+/// `file://` URIs that also shows up in the plain-text clipboard, so a read where
+/// every non-blank line is a `file://` URI is treated as a file list rather than text.
+fn classify_text(text: String) -> ContentKind {
+    let lines: Vec<&str> = text.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+
+    if !lines.is_empty() && lines.iter().all(|line| line.starts_with("file://")) {
+        ContentKind::Files(lines.into_iter().map(file_uri_to_path).collect())
+    } else {
+        ContentKind::Text(text)
+    }
+}
+
+/// Strip the `file://` scheme and percent-decode a `text/uri-list` entry into a
+/// filesystem path
+fn file_uri_to_path(uri: &str) -> String {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    percent_decode(path)
+}
+
+fn percent_decode(s: &str) -> String {
+    // Work on raw bytes throughout: `%` is always introducing an ASCII hex escape of a
+    // single byte, but the bytes around it are not guaranteed to fall on a `char`
+    // boundary (e.g. a literal multi-byte character right after a stray `%`), so
+    // slicing `s` by byte offset can panic. Hex-nibble-decode by hand instead.
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(hi), Some(lo)) = (hex_nibble(bytes[i + 1]), hex_nibble(bytes[i + 2])) {
+                out.push((hi << 4) | lo);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Decode a single ASCII hex digit to its nibble value
+fn hex_nibble(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
 /// Clipboard monitor state
 pub struct ClipboardMonitor {
     running: Arc<AtomicBool>,
-    last_content: Arc<Mutex<String>>,
+    // Hash of whatever was last read, text or image, so a tick can skip re-emitting
+    // unchanged clipboard content without ever touching the database. Shared across
+    // both kinds rather than tracked per-kind, so copying A, then something else, then
+    // A again is still seen as a change — a per-kind hash would miss it, since A's
+    // kind-specific hash would still match from before the other kind was copied.
+    last_hash: Arc<AtomicU64>,
 }
 
 impl ClipboardMonitor {
     pub fn new() -> Self {
         Self {
             running: Arc::new(AtomicBool::new(false)),
-            last_content: Arc::new(Mutex::new(String::new())),
+            last_hash: Arc::new(AtomicU64::new(0)),
         }
     }
 
     /// Start monitoring the clipboard in the background
     pub fn start<F>(&self, on_new_content: F)
     where
-        F: Fn(String) + Send + 'static,
+        F: Fn(ContentKind) + Send + 'static,
     {
         if self.running.swap(true, Ordering::SeqCst) {
             // Already running
@@ -203,17 +615,32 @@ impl ClipboardMonitor {
         }
 
         let running = self.running.clone();
-        let last_content = self.last_content.clone();
+        let last_hash = self.last_hash.clone();
 
         async_runtime::spawn(async move {
+            // `arboard::Clipboard` isn't trivially `Send`, so it's constructed here,
+            // inside the task, instead of being held on `ClipboardMonitor`.
+            let mut clipboard = match Clipboard::new() {
+                Ok(clipboard) => clipboard,
+                Err(e) => {
+                    eprintln!("{}", ClipboardBackend::Unavailable(e.to_string()));
+                    running.store(false, Ordering::SeqCst);
+                    return;
+                }
+            };
+
             while running.load(Ordering::SeqCst) {
-                // Read clipboard using tauri's clipboard plugin
-                if let Ok(content) = read_system_clipboard().await {
-                    let mut last = last_content.lock().unwrap();
-                    if !content.is_empty() && content != *last {
-                        *last = content.clone();
-                        drop(last); // Release lock before callback
-                        on_new_content(content);
+                if let Ok(text) = read_system_clipboard(&mut clipboard) {
+                    if !text.is_empty() {
+                        let hash = content_hash(text.as_bytes());
+                        if last_hash.swap(hash, Ordering::SeqCst) != hash {
+                            on_new_content(classify_text(text));
+                        }
+                    }
+                } else if let Ok((width, height, rgba)) = read_system_clipboard_image(&mut clipboard) {
+                    let hash = content_hash(&rgba);
+                    if last_hash.swap(hash, Ordering::SeqCst) != hash {
+                        on_new_content(ContentKind::Image { width, height, rgba });
                     }
                 }
 
@@ -234,26 +661,83 @@ impl ClipboardMonitor {
     }
 }
 
-/// Read clipboard content from system
-async fn read_system_clipboard() -> Result<String, String> {
-    // Use macOS pbpaste for clipboard reading
-    #[cfg(target_os = "macos")]
-    {
-        let output = tokio::process::Command::new("pbpaste")
-            .output()
-            .await
-            .map_err(|e| e.to_string())?;
-
-        if output.status.success() {
-            String::from_utf8(output.stdout).map_err(|e| e.to_string())
-        } else {
-            Err("Failed to read clipboard".to_string())
+/// Read the current text content of the system clipboard
+fn read_system_clipboard(clipboard: &mut Clipboard) -> Result<String, ClipboardBackend> {
+    clipboard
+        .get_text()
+        .map_err(|e| ClipboardBackend::Unavailable(e.to_string()))
+}
+
+/// Read the current image content of the system clipboard as raw RGBA pixels
+fn read_system_clipboard_image(
+    clipboard: &mut Clipboard,
+) -> Result<(u32, u32, Vec<u8>), ClipboardBackend> {
+    let image = clipboard
+        .get_image()
+        .map_err(|e| ClipboardBackend::Unavailable(e.to_string()))?;
+
+    Ok((
+        image.width as u32,
+        image.height as u32,
+        image.bytes.into_owned(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_decode_handles_escapes() {
+        assert_eq!(percent_decode("hello%20world"), "hello world");
+        assert_eq!(percent_decode("no-escapes"), "no-escapes");
+    }
+
+    #[test]
+    fn percent_decode_does_not_panic_on_multibyte_char_after_percent() {
+        // A literal `%` immediately followed by a multi-byte UTF-8 character used to
+        // panic: slicing the next two bytes by str index landed mid-character.
+        assert_eq!(percent_decode("100%€.txt"), "100%€.txt");
+        assert_eq!(percent_decode("résumé%"), "résumé%");
+    }
+
+    #[test]
+    fn percent_decode_passes_through_invalid_escapes() {
+        assert_eq!(percent_decode("100%-off"), "100%-off");
+    }
+
+    #[test]
+    fn file_uri_to_path_strips_scheme_and_decodes() {
+        assert_eq!(file_uri_to_path("file:///home/user/100%25.txt"), "/home/user/100%.txt");
+    }
+
+    #[test]
+    fn classify_text_detects_file_uri_lists() {
+        let uris = "file:///home/user/a.txt\nfile:///home/user/b.png".to_string();
+        match classify_text(uris) {
+            ContentKind::Files(paths) => {
+                assert_eq!(paths, vec!["/home/user/a.txt", "/home/user/b.png"]);
+            }
+            _ => panic!("expected ContentKind::Files"),
         }
     }
 
-    #[cfg(not(target_os = "macos"))]
-    {
-        // For other platforms, we'd need platform-specific implementations
-        Err("Clipboard monitoring not supported on this platform".to_string())
+    #[test]
+    fn classify_text_leaves_plain_text_alone() {
+        match classify_text("just some copied text".to_string()) {
+            ContentKind::Text(text) => assert_eq!(text, "just some copied text"),
+            _ => panic!("expected ContentKind::Text"),
+        }
+    }
+
+    #[test]
+    fn build_fts_query_prefix_matches_each_term() {
+        assert_eq!(build_fts_query("hash map"), "\"hash\"* \"map\"*");
+        assert_eq!(build_fts_query(""), "");
+    }
+
+    #[test]
+    fn build_fts_query_escapes_embedded_quotes() {
+        assert_eq!(build_fts_query("say \"hi\""), "\"say\"* \"\"\"hi\"\"\"*");
     }
 }
@@ -0,0 +1,462 @@
+//! Opt-in, end-to-end encrypted sync of clipboard history between a user's devices.
+//!
+//! Modeled on atuin's history sync: every `clipboard_history` row carries a stable
+//! UUID (see `clipboard_history::init_clipboard_table`), content is encrypted
+//! client-side before it ever leaves the device, and the server only ever stores
+//! opaque ciphertext. Each device tracks its own high-water marks in `sync_state`:
+//! `push` sends only rows with a higher id than its last push (a local row whose
+//! `created_at` was bumped is not re-sent), and `pull` asks only for records created
+//! after its last pull.
+
+use crate::clipboard_history::{content_hash, find_duplicate_by_hash};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+use sqlx::Row;
+use uuid::Uuid;
+
+/// Length in bytes of the per-account KDF salt produced by [`generate_account_salt`]
+pub const KDF_SALT_LEN: usize = 16;
+
+/// Generate a random per-account KDF salt. Call this once, when sync is first enabled
+/// for an account, and distribute the result to every device the same way the
+/// passphrase itself is shared (e.g. alongside the account's sync server
+/// credentials) — every device needs the same salt to derive the same key, but unlike
+/// the passphrase it isn't secret. A salt shared across all accounts would make the
+/// derived key cheaper to attack via precomputation; a per-account salt doesn't.
+pub fn generate_account_salt() -> [u8; KDF_SALT_LEN] {
+    let mut salt = [0u8; KDF_SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+/// Sync configuration for one device. `encryption_key` is derived once via
+/// [`SyncConfig::derive`] and held only in memory — the passphrase itself is never
+/// stored or transmitted.
+pub struct SyncConfig {
+    pub server_url: String,
+    pub device_id: Uuid,
+    pub auth_token: String,
+    encryption_key: [u8; 32],
+}
+
+impl SyncConfig {
+    /// Derive the symmetric encryption key from the user's passphrase and their
+    /// account's salt (see [`generate_account_salt`]). Every device that knows both
+    /// derives the same key, independent of `device_id`.
+    pub fn derive(
+        server_url: String,
+        device_id: Uuid,
+        auth_token: String,
+        passphrase: &str,
+        salt: &[u8],
+    ) -> Result<Self, SyncError> {
+        let mut encryption_key = [0u8; 32];
+        argon2::Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut encryption_key)
+            .map_err(|e| SyncError::KeyDerivation(e.to_string()))?;
+
+        Ok(Self {
+            server_url,
+            device_id,
+            auth_token,
+            encryption_key,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum SyncError {
+    KeyDerivation(String),
+    Encryption(String),
+    Decryption(String),
+    Transport(String),
+    Storage(sqlx::Error),
+}
+
+impl std::fmt::Display for SyncError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncError::KeyDerivation(e) => write!(f, "key derivation failed: {}", e),
+            SyncError::Encryption(e) => write!(f, "encryption failed: {}", e),
+            SyncError::Decryption(e) => write!(f, "decryption failed: {}", e),
+            SyncError::Transport(e) => write!(f, "sync server request failed: {}", e),
+            SyncError::Storage(e) => write!(f, "sync storage error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SyncError {}
+
+impl From<sqlx::Error> for SyncError {
+    fn from(e: sqlx::Error) -> Self {
+        SyncError::Storage(e)
+    }
+}
+
+impl From<reqwest::Error> for SyncError {
+    fn from(e: reqwest::Error) -> Self {
+        SyncError::Transport(e.to_string())
+    }
+}
+
+/// Everything about an entry that's sensitive, bundled up and encrypted as one blob
+/// before it ever reaches the server.
+#[derive(Debug, Serialize, Deserialize)]
+struct EntryPayload {
+    content_type: String,
+    content: Option<String>,
+    content_blob: Option<Vec<u8>>,
+    preview_blob: Option<Vec<u8>>,
+    preview: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    char_count: Option<i32>,
+}
+
+/// Wire format: the server sees a UUID, a timestamp (needed for conflict resolution)
+/// and ciphertext/nonce. Nothing else.
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedRecord {
+    uuid: Uuid,
+    created_at: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Serialize)]
+struct PushRequest {
+    device_id: Uuid,
+    records: Vec<EncryptedRecord>,
+}
+
+#[derive(Deserialize)]
+struct PullResponse {
+    records: Vec<EncryptedRecord>,
+}
+
+/// Result of a combined [`sync`] call
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SyncStats {
+    pub pushed: u32,
+    pub pulled: u32,
+}
+
+/// Initialize the `sync_state` table that tracks each device's high-water mark
+pub async fn init_sync_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS sync_state (
+            device_id TEXT PRIMARY KEY,
+            last_pushed_row_id INTEGER NOT NULL DEFAULT 0,
+            last_pulled_at TEXT
+        )",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+fn encrypt_payload(key: &[u8; 32], payload: &EntryPayload) -> Result<(Vec<u8>, Vec<u8>), SyncError> {
+    let cipher = XChaCha20Poly1305::new(key.into());
+
+    let mut nonce_bytes = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = XNonce::from_slice(&nonce_bytes);
+
+    let plaintext = serde_json::to_vec(payload).map_err(|e| SyncError::Encryption(e.to_string()))?;
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext.as_ref())
+        .map_err(|e| SyncError::Encryption(e.to_string()))?;
+
+    Ok((nonce_bytes.to_vec(), ciphertext))
+}
+
+fn decrypt_payload(key: &[u8; 32], nonce: &[u8], ciphertext: &[u8]) -> Result<EntryPayload, SyncError> {
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let nonce = XNonce::from_slice(nonce);
+
+    let plaintext = cipher
+        .decrypt(nonce, ciphertext)
+        .map_err(|e| SyncError::Decryption(e.to_string()))?;
+
+    serde_json::from_slice(&plaintext).map_err(|e| SyncError::Decryption(e.to_string()))
+}
+
+/// Push local entries newer than this device's high-water mark. Returns the number
+/// of records pushed.
+pub async fn push(pool: &SqlitePool, config: &SyncConfig) -> Result<u32, SyncError> {
+    let last_pushed_row_id = get_last_pushed_row_id(pool, config.device_id).await?;
+
+    let rows = sqlx::query(
+        "SELECT id, uuid, content_type, content, content_blob, preview_blob, preview, width, height, char_count, created_at
+         FROM clipboard_history
+         WHERE id > ?
+         ORDER BY id ASC",
+    )
+    .bind(last_pushed_row_id)
+    .fetch_all(pool)
+    .await?;
+
+    if rows.is_empty() {
+        return Ok(0);
+    }
+
+    let highest_id = rows
+        .iter()
+        .map(|row| row.try_get::<i64, _>("id"))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .max()
+        .unwrap();
+
+    let mut records = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let uuid: String = row.try_get("uuid")?;
+        let payload = EntryPayload {
+            content_type: row.try_get("content_type")?,
+            content: row.try_get("content")?,
+            content_blob: row.try_get("content_blob")?,
+            preview_blob: row.try_get("preview_blob")?,
+            // Text and file rows leave `preview` NULL; only image rows store one
+            preview: row.try_get::<Option<String>, _>("preview")?.unwrap_or_default(),
+            width: row.try_get::<Option<i64>, _>("width")?.map(|w| w as u32),
+            height: row.try_get::<Option<i64>, _>("height")?.map(|h| h as u32),
+            char_count: row.try_get("char_count")?,
+        };
+        let created_at: String = row.try_get("created_at")?;
+
+        let (nonce, ciphertext) = encrypt_payload(&config.encryption_key, &payload)?;
+        records.push(EncryptedRecord {
+            uuid: Uuid::parse_str(&uuid).map_err(|e| SyncError::Encryption(e.to_string()))?,
+            created_at,
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        });
+    }
+
+    let count = records.len() as u32;
+
+    reqwest::Client::new()
+        .post(format!("{}/records", config.server_url))
+        .bearer_auth(&config.auth_token)
+        .json(&PushRequest {
+            device_id: config.device_id,
+            records,
+        })
+        .send()
+        .await?
+        .error_for_status()?;
+
+    set_last_pushed_row_id(pool, config.device_id, highest_id).await?;
+
+    Ok(count)
+}
+
+/// Pull remote entries newer than this device's last pull and merge them into
+/// `clipboard_history`. A pulled record whose content hash already matches a local
+/// row — e.g. the same copy captured independently on two devices, each under its own
+/// UUID — collapses into that row instead of inserting a second copy; otherwise it's
+/// upserted by UUID, with conflicts (the same UUID synced twice) resolved by keeping
+/// whichever copy has the newer `created_at`. Returns the number of records pulled.
+pub async fn pull(pool: &SqlitePool, config: &SyncConfig) -> Result<u32, SyncError> {
+    let since = get_last_pulled_at(pool, config.device_id).await?;
+
+    let response: PullResponse = reqwest::Client::new()
+        .get(format!("{}/records", config.server_url))
+        .bearer_auth(&config.auth_token)
+        .query(&[("since", since.as_deref().unwrap_or(""))])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let mut latest_created_at = since;
+
+    for record in &response.records {
+        let nonce = BASE64
+            .decode(&record.nonce)
+            .map_err(|e| SyncError::Decryption(e.to_string()))?;
+        let ciphertext = BASE64
+            .decode(&record.ciphertext)
+            .map_err(|e| SyncError::Decryption(e.to_string()))?;
+        let payload = decrypt_payload(&config.encryption_key, &nonce, &ciphertext)?;
+
+        // The hash column is dedup bookkeeping, not sync state, so it's recomputed
+        // locally from the decrypted content rather than carried over the wire.
+        let hash = payload
+            .content
+            .as_deref()
+            .map(|c| content_hash(c.as_bytes()))
+            .or_else(|| {
+                // Local image rows hash the raw RGBA pixels, not the stored PNG, so
+                // decode back to pixels for the two hashes to line up
+                payload.content_blob.as_deref().map(|blob| match image::load_from_memory(blob) {
+                    Ok(img) => content_hash(img.to_rgba8().as_raw()),
+                    Err(_) => content_hash(blob),
+                })
+            })
+            .map(|h| h as i64);
+
+        let folded = match hash {
+            Some(h) => find_duplicate_by_hash(pool, &payload.content_type, h).await?,
+            None => None,
+        };
+
+        if let Some((existing_id, existing_created_at)) = folded {
+            // Same content already present locally — possibly captured independently
+            // on another device under a different UUID — so collapse into that row
+            // instead of inserting a second copy.
+            if record.created_at.as_str() > existing_created_at.as_str() {
+                sqlx::query("UPDATE clipboard_history SET created_at = ? WHERE id = ?")
+                    .bind(&record.created_at)
+                    .bind(existing_id)
+                    .execute(pool)
+                    .await?;
+            }
+        } else {
+            sqlx::query(
+                "INSERT INTO clipboard_history
+                    (uuid, content_type, content, content_blob, content_hash, preview_blob, preview, width, height, char_count, created_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                 ON CONFLICT(uuid) DO UPDATE SET
+                    content_type = excluded.content_type,
+                    content = excluded.content,
+                    content_blob = excluded.content_blob,
+                    content_hash = excluded.content_hash,
+                    preview_blob = excluded.preview_blob,
+                    preview = excluded.preview,
+                    width = excluded.width,
+                    height = excluded.height,
+                    char_count = excluded.char_count,
+                    created_at = excluded.created_at
+                 WHERE excluded.created_at > clipboard_history.created_at",
+            )
+            .bind(record.uuid.to_string())
+            .bind(&payload.content_type)
+            .bind(&payload.content)
+            .bind(&payload.content_blob)
+            .bind(hash)
+            .bind(&payload.preview_blob)
+            .bind(&payload.preview)
+            .bind(payload.width.map(|w| w as i64))
+            .bind(payload.height.map(|h| h as i64))
+            .bind(payload.char_count)
+            .bind(&record.created_at)
+            .execute(pool)
+            .await?;
+        }
+
+        if latest_created_at
+            .as_deref()
+            .map_or(true, |latest| record.created_at.as_str() > latest)
+        {
+            latest_created_at = Some(record.created_at.clone());
+        }
+    }
+
+    let pulled = response.records.len() as u32;
+
+    if let Some(latest) = latest_created_at {
+        set_last_pulled_at(pool, config.device_id, &latest).await?;
+    }
+
+    Ok(pulled)
+}
+
+/// Push this device's new entries, then pull everyone else's
+pub async fn sync(pool: &SqlitePool, config: &SyncConfig) -> Result<SyncStats, SyncError> {
+    let pushed = push(pool, config).await?;
+    let pulled = pull(pool, config).await?;
+    Ok(SyncStats { pushed, pulled })
+}
+
+async fn get_last_pushed_row_id(pool: &SqlitePool, device_id: Uuid) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT last_pushed_row_id FROM sync_state WHERE device_id = ?")
+        .bind(device_id.to_string())
+        .fetch_optional(pool)
+        .await
+        .map(|row_id| row_id.unwrap_or(0))
+}
+
+async fn set_last_pushed_row_id(pool: &SqlitePool, device_id: Uuid, row_id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO sync_state (device_id, last_pushed_row_id) VALUES (?, ?)
+         ON CONFLICT(device_id) DO UPDATE SET last_pushed_row_id = excluded.last_pushed_row_id",
+    )
+    .bind(device_id.to_string())
+    .bind(row_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn get_last_pulled_at(pool: &SqlitePool, device_id: Uuid) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT last_pulled_at FROM sync_state WHERE device_id = ?")
+        .bind(device_id.to_string())
+        .fetch_optional(pool)
+        .await
+        .map(|last_pulled_at: Option<Option<String>>| last_pulled_at.flatten())
+}
+
+async fn set_last_pulled_at(pool: &SqlitePool, device_id: Uuid, created_at: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO sync_state (device_id, last_pushed_row_id, last_pulled_at) VALUES (?, 0, ?)
+         ON CONFLICT(device_id) DO UPDATE SET last_pulled_at = excluded.last_pulled_at",
+    )
+    .bind(device_id.to_string())
+    .bind(created_at)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_payload() -> EntryPayload {
+        EntryPayload {
+            content_type: "text".to_string(),
+            content: Some("hello from another device".to_string()),
+            content_blob: None,
+            preview_blob: None,
+            preview: "hello from another device".to_string(),
+            width: None,
+            height: None,
+            char_count: Some(25),
+        }
+    }
+
+    #[test]
+    fn encrypt_decrypt_round_trip_recovers_the_payload() {
+        let key = [7u8; 32];
+        let payload = test_payload();
+
+        let (nonce, ciphertext) = encrypt_payload(&key, &payload).expect("encrypt");
+        let decrypted = decrypt_payload(&key, &nonce, &ciphertext).expect("decrypt");
+
+        assert_eq!(decrypted.content, payload.content);
+        assert_eq!(decrypted.content_type, payload.content_type);
+        assert_eq!(decrypted.char_count, payload.char_count);
+    }
+
+    #[test]
+    fn decrypt_rejects_ciphertext_under_the_wrong_key() {
+        let payload = test_payload();
+        let (nonce, ciphertext) = encrypt_payload(&[1u8; 32], &payload).expect("encrypt");
+
+        assert!(decrypt_payload(&[2u8; 32], &nonce, &ciphertext).is_err());
+    }
+
+    #[test]
+    fn account_salt_is_unique_per_call() {
+        let a = generate_account_salt();
+        let b = generate_account_salt();
+        assert_ne!(a, b);
+    }
+}